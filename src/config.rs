@@ -0,0 +1,248 @@
+use std::fs;
+use std::net::IpAddr;
+
+use serde::Deserialize;
+
+use lazy_static::lazy_static;
+
+const CONFIG_PATH: &str = "/etc/libnss-netns.conf";
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct RawConfig {
+    excluded_scopes: Vec<String>,
+    include_loopback: bool,
+    preferred_family: String,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    suffix: String,
+    strict_suffix: bool,
+    neighbor_discovery: bool,
+    neighbor_template: String,
+}
+
+impl Default for RawConfig {
+    fn default() -> Self {
+        RawConfig {
+            excluded_scopes: vec!["link".to_string()],
+            include_loopback: false,
+            preferred_family: "inet".to_string(),
+            allow: vec![],
+            deny: vec![],
+            suffix: String::new(),
+            strict_suffix: false,
+            neighbor_discovery: false,
+            neighbor_template: "<ns>-<dev>-<index>".to_string(),
+        }
+    }
+}
+
+/// Resolved filtering policy for the address pipeline in
+/// `get_host_by_name`. Loaded once from [`CONFIG_PATH`].
+/// `excluded_scopes` is a deny-list rather than an allow-list so that an
+/// unconfigured install keeps seeing scopes it doesn't know the name of.
+#[derive(Debug)]
+pub(crate) struct Config {
+    pub excluded_scopes: Vec<String>,
+    pub include_loopback: bool,
+    pub preferred_family: String,
+    pub allow: Vec<Cidr>,
+    pub deny: Vec<Cidr>,
+    pub suffix: String,
+    pub strict_suffix: bool,
+    pub neighbor_discovery: bool,
+    pub neighbor_template: String,
+}
+
+impl Config {
+    fn load() -> Config {
+        let raw = match fs::read_to_string(CONFIG_PATH) {
+            Ok(contents) => match toml::from_str(&contents) {
+                Ok(raw) => raw,
+                Err(err) => {
+                    eprintln!("config: failed to parse {}: {}", CONFIG_PATH, err);
+                    RawConfig::default()
+                },
+            },
+            Err(_) => RawConfig::default(),
+        };
+
+        Config {
+            excluded_scopes: raw.excluded_scopes,
+            include_loopback: raw.include_loopback,
+            preferred_family: raw.preferred_family,
+            allow: parse_rules(&raw.allow),
+            deny: parse_rules(&raw.deny),
+            suffix: raw.suffix,
+            strict_suffix: raw.strict_suffix,
+            neighbor_discovery: raw.neighbor_discovery,
+            neighbor_template: raw.neighbor_template,
+        }
+    }
+
+    /// Renders the neighbor hostname template, substituting `<ns>`,
+    /// `<dev>`, `<index>` and `<lladdr>`.
+    pub fn neighbor_name(&self, ns: &str, dev: &str, index: usize, lladdr: Option<&str>) -> String {
+        let lladdr = lladdr.map(|l| l.replace(':', "")).unwrap_or_default();
+
+        self.neighbor_template
+            .replace("<ns>", ns)
+            .replace("<dev>", dev)
+            .replace("<index>", &index.to_string())
+            .replace("<lladdr>", &lladdr)
+    }
+
+    /// Whether `addr` survives the configured allow/deny CIDR rules. A
+    /// deny match always wins; otherwise the address passes if the allow
+    /// list is empty or it matches one of its entries.
+    pub fn permits(&self, addr: &IpAddr) -> bool {
+        if self.deny.iter().any(|rule| rule.contains(addr)) {
+            return false;
+        }
+
+        self.allow.is_empty() || self.allow.iter().any(|rule| rule.contains(addr))
+    }
+
+    /// Strips the configured domain suffix from a queried name. `Err`
+    /// means a suffix is configured but `name` didn't carry it.
+    pub fn strip_suffix<'a>(&self, name: &'a str) -> Result<&'a str, ()> {
+        if self.suffix.is_empty() {
+            return Ok(name);
+        }
+
+        name.strip_suffix(self.suffix.as_str()).ok_or(())
+    }
+}
+
+fn parse_rules(rules: &[String]) -> Vec<Cidr> {
+    rules.iter().filter_map(|rule| match Cidr::parse(rule) {
+        Some(cidr) => Some(cidr),
+        None => {
+            eprintln!("config: ignoring unparseable CIDR rule {:?}", rule);
+            None
+        },
+    }).collect()
+}
+
+/// A parsed `"a.b.c.d/len"` (or IPv6 equivalent) rule, kept as the masked
+/// network address plus its prefix length so membership tests are a mask
+/// and compare instead of a re-parse.
+#[derive(Debug, Clone)]
+pub(crate) enum Cidr {
+    V4 { network: u32, prefix_len: u8 },
+    V6 { network: u128, prefix_len: u8 },
+}
+
+impl Cidr {
+    pub fn parse(rule: &str) -> Option<Cidr> {
+        let (addr_part, len_part) = rule.split_once('/')?;
+        let prefix_len: u8 = len_part.parse().ok()?;
+
+        match addr_part.parse().ok()? {
+            IpAddr::V4(addr) => {
+                if prefix_len > 32 { return None; }
+                Some(Cidr::V4 { network: u32::from(addr) & mask32(prefix_len), prefix_len })
+            },
+
+            IpAddr::V6(addr) => {
+                if prefix_len > 128 { return None; }
+                Some(Cidr::V6 { network: u128::from(addr) & mask128(prefix_len), prefix_len })
+            },
+        }
+    }
+
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        match (self, addr) {
+            (Cidr::V4 { network, prefix_len }, IpAddr::V4(addr)) =>
+                u32::from(*addr) & mask32(*prefix_len) == *network,
+
+            (Cidr::V6 { network, prefix_len }, IpAddr::V6(addr)) =>
+                u128::from(*addr) & mask128(*prefix_len) == *network,
+
+            _ => false,
+        }
+    }
+}
+
+fn mask32(prefix_len: u8) -> u32 {
+    if prefix_len == 0 { 0 } else { !0u32 << (32 - prefix_len) }
+}
+
+fn mask128(prefix_len: u8) -> u128 {
+    if prefix_len == 0 { 0 } else { !0u128 << (128 - prefix_len) }
+}
+
+lazy_static! {
+    pub(crate) static ref CONFIG: Config = Config::load();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_v4_and_v6_rules() {
+        assert!(matches!(Cidr::parse("10.0.0.0/8"), Some(Cidr::V4 { .. })));
+        assert!(matches!(Cidr::parse("fd00::/16"), Some(Cidr::V6 { .. })));
+    }
+
+    #[test]
+    fn rejects_malformed_or_out_of_range_rules() {
+        assert!(Cidr::parse("not-an-addr/8").is_none());
+        assert!(Cidr::parse("10.0.0.0").is_none());
+        assert!(Cidr::parse("10.0.0.0/33").is_none());
+        assert!(Cidr::parse("fd00::/129").is_none());
+    }
+
+    #[test]
+    fn prefix_len_zero_matches_everything() {
+        let cidr = Cidr::parse("0.0.0.0/0").unwrap();
+        assert!(cidr.contains(&"1.2.3.4".parse().unwrap()));
+        assert!(cidr.contains(&"255.255.255.255".parse().unwrap()));
+    }
+
+    #[test]
+    fn prefix_len_full_matches_only_exact_address() {
+        let cidr = Cidr::parse("10.0.0.1/32").unwrap();
+        assert!(cidr.contains(&"10.0.0.1".parse().unwrap()));
+        assert!(!cidr.contains(&"10.0.0.2".parse().unwrap()));
+    }
+
+    #[test]
+    fn contains_checks_family_match() {
+        let v4 = Cidr::parse("10.0.0.0/8").unwrap();
+        assert!(!v4.contains(&"::1".parse().unwrap()));
+    }
+
+    fn config_with_suffix(suffix: &str) -> Config {
+        Config {
+            excluded_scopes: vec![],
+            include_loopback: false,
+            preferred_family: "inet".to_string(),
+            allow: vec![],
+            deny: vec![],
+            suffix: suffix.to_string(),
+            strict_suffix: false,
+            neighbor_discovery: false,
+            neighbor_template: String::new(),
+        }
+    }
+
+    #[test]
+    fn strip_suffix_passes_name_through_when_unconfigured() {
+        let config = config_with_suffix("");
+        assert_eq!(config.strip_suffix("web.netns"), Ok("web.netns"));
+    }
+
+    #[test]
+    fn strip_suffix_strips_matching_suffix() {
+        let config = config_with_suffix(".netns");
+        assert_eq!(config.strip_suffix("web.netns"), Ok("web"));
+    }
+
+    #[test]
+    fn strip_suffix_rejects_name_without_suffix() {
+        let config = config_with_suffix(".netns");
+        assert_eq!(config.strip_suffix("web"), Err(()));
+    }
+}
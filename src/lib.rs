@@ -1,5 +1,7 @@
-use std::process::Command;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use libnss::host::{HostHooks, Host, Addresses, AddressFamily};
 use libnss::interop::Response;
@@ -9,48 +11,135 @@ use serde::Deserialize;
 #[macro_use]
 extern crate lazy_static;
 
+mod config;
+mod source;
+
+use config::CONFIG;
+use source::{ActiveSource, NetnsSource};
+
 struct NsHost;
 
-#[derive(Deserialize)]
-struct LinkAddress {
+#[derive(Deserialize, Clone)]
+pub(crate) struct LinkAddress {
     pub family: String,
     pub local: String,
     pub scope: String,
 }
 
-#[derive(Deserialize)]
-struct LinkInfo {
+#[derive(Deserialize, Clone)]
+pub(crate) struct LinkInfo {
     pub link_type: String,
     pub addr_info: Vec::<LinkAddress>,
 }
 
 #[derive(Deserialize)]
-struct Netns {
+#[cfg_attr(feature = "netlink", allow(dead_code))]
+pub(crate) struct Netns {
     pub name: String,
 }
 
+/// One entry from `ip -n <ns> -json neigh`.
+#[derive(Deserialize, Clone)]
+pub(crate) struct Neighbor {
+    pub dst: IpAddr,
+    pub dev: String,
+    pub lladdr: Option<String>,
+    #[serde(default)]
+    pub state: Vec<String>,
+}
+
+const NEIGHBOR_REACHABLE_STATES: [&str; 3] = ["REACHABLE", "STALE", "DELAY"];
+
+impl Neighbor {
+    fn is_reachable(&self) -> bool {
+        self.state.iter().any(|s| NEIGHBOR_REACHABLE_STATES.contains(&s.as_str()))
+    }
+}
+
+// How long a cached `ip netns`/`ip address` snapshot may be reused.
+const CACHE_TTL: Duration = Duration::from_secs(5);
+
+type AddrCache = HashMap<Option<String>, (Vec<LinkInfo>, Instant)>;
+type NeighCache = HashMap<Option<String>, (Vec<Neighbor>, Instant)>;
+
+lazy_static! {
+    static ref SOURCE: ActiveSource = ActiveSource::default();
+    static ref NETNS_LIST_CACHE: Mutex<Option<(Vec::<String>, Instant)>> = Mutex::new(None);
+    static ref NETNS_ADDR_CACHE: Mutex<AddrCache> = Mutex::new(HashMap::new());
+    static ref NETNS_NEIGH_CACHE: Mutex<NeighCache> = Mutex::new(HashMap::new());
+}
+
+fn cached_ip_netns_ls() -> Result::<Vec::<String>, Box::<dyn std::error::Error>> {
+    let mut cache = NETNS_LIST_CACHE.lock().unwrap();
+
+    if let Some((names, fetched_at)) = cache.as_ref() {
+        if fetched_at.elapsed() < CACHE_TTL {
+            return Ok(names.clone());
+        }
+    }
+
+    let names = SOURCE.list_namespaces()?;
+    *cache = Some((names.clone(), Instant::now()));
+    Ok(names)
+}
+
+fn cached_ip_addr(netns: Option<&str>) -> Result::<Vec::<LinkInfo>, Box::<dyn std::error::Error>> {
+    let key = netns.map(String::from);
+    let mut cache = NETNS_ADDR_CACHE.lock().unwrap();
+
+    if let Some((links, fetched_at)) = cache.get(&key) {
+        if fetched_at.elapsed() < CACHE_TTL {
+            return Ok(links.clone());
+        }
+    }
+
+    let links = SOURCE.addresses(netns)?;
+    cache.insert(key, (links.clone(), Instant::now()));
+    Ok(links)
+}
+
+fn cached_ip_neigh(netns: Option<&str>) -> Result::<Vec::<Neighbor>, Box::<dyn std::error::Error>> {
+    let key = netns.map(String::from);
+    let mut cache = NETNS_NEIGH_CACHE.lock().unwrap();
+
+    if let Some((neighbors, fetched_at)) = cache.get(&key) {
+        if fetched_at.elapsed() < CACHE_TTL {
+            return Ok(neighbors.clone());
+        }
+    }
+
+    let neighbors = SOURCE.neighbors(netns)?;
+    cache.insert(key, (neighbors.clone(), Instant::now()));
+    Ok(neighbors)
+}
+
 libnss::libnss_host_hooks!(netns, NsHost);
 
 impl HostHooks for NsHost {
     fn get_all_entries() -> libnss::interop::Response::<Vec::<Host>> {
         fn get_for_ns(name: &str) -> Vec::<Host> {
-            let recv4 = NsHost::get_host_by_name(name, AddressFamily::IPv4);
-            let recv6 = NsHost::get_host_by_name(name, AddressFamily::IPv6);
-
-            let mut host_recs = vec![];
-
-            if let Response::Success(rec) = recv4 {
-                host_recs.push(rec);
-            }
+            let families = if CONFIG.preferred_family == "inet6" {
+                vec![AddressFamily::IPv6, AddressFamily::IPv4]
+            } else {
+                vec![AddressFamily::IPv4, AddressFamily::IPv6]
+            };
+
+            let mut hosts: Vec::<Host> = families
+                .into_iter()
+                .filter_map(|family| match resolve_host(name, family) {
+                    Response::Success(rec) => Some(rec),
+                    _ => None,
+                })
+                .collect();
 
-            if let Response::Success(rec) = recv6 {
-                host_recs.push(rec);
+            if CONFIG.neighbor_discovery {
+                hosts.extend(neighbor_hosts(name));
             }
 
-            host_recs
+            hosts
         }
 
-        let ns_names = ip_netns_ls();
+        let ns_names = cached_ip_netns_ls();
         if let Err(err) = ns_names {
             eprintln!("get_all_entries: {}", err);
             return Response::Unavail;
@@ -63,76 +152,24 @@ impl HostHooks for NsHost {
     }
 
     fn get_host_by_name(name: &str, family: libnss::host::AddressFamily) -> libnss::interop::Response<Host> {
-        let result = ip_addr(Some(name));
-        if let Err(err) = result {
-            eprintln!("get_host_by_name: {}", err);
-            return Response::NotFound;
-        }
-
-        let af_filter = match family {
-            libnss::host::AddressFamily::IPv6 => "inet6",
-            libnss::host::AddressFamily::IPv4 | _ => "inet",
+        let ns_name = match CONFIG.strip_suffix(name) {
+            Ok(ns_name) => Some(ns_name),
+            Err(()) => if CONFIG.strict_suffix { None } else { Some(name) },
         };
 
-        let result = result.unwrap();
-
-        let addresses: Vec::<IpAddr> =
-            result
-            .into_iter()
-            .filter(|info| info.link_type != "loopback")
-            .map(|link: LinkInfo| {
-                let addr_info: Vec::<IpAddr> =
-                    link
-                    .addr_info
-                    .into_iter()
-                    .filter(|addr| addr.family == af_filter)
-                    .filter(|addr| addr.scope != "link")
-                    .map(|addr| {
-                        let parsed: IpAddr = addr.local.parse().expect("not an IP address");
-                        parsed
-                    })
-                    .collect();
-                addr_info
-            })
-            .flatten()
-            .collect()
-        ;
-
-        match family {
-            AddressFamily::IPv6 => {
-                let addresses: Vec::<Ipv6Addr> = addresses.into_iter().map(|a| match a {
-                    IpAddr::V6(a) => a,
-                    _ => unreachable!(),
-                }).collect();
-
-                if addresses.is_empty() {
-                    return Response::NotFound;
-                }
-
-                Response::Success(Host{
-                    name: name.into(),
-                    aliases: vec![],
-                    addresses: Addresses::V6(addresses),
-                })
-            },
-
-            AddressFamily::IPv4 | _ => {
-                let addresses: Vec::<Ipv4Addr> = addresses.into_iter().map(|a| match a {
-                    IpAddr::V4(a) => a,
-                    _ => unreachable!(),
-                }).collect();
-
-                if addresses.is_empty() {
-                    return Response::NotFound;
-                }
+        if let Some(ns_name) = ns_name {
+            if let Response::Success(host) = resolve_host(ns_name, copy_family(&family)) {
+                return Response::Success(host);
+            }
+        }
 
-                Response::Success(Host{
-                    name: name.into(),
-                    aliases: vec![],
-                    addresses: Addresses::V4(addresses),
-                })
-            },
+        if CONFIG.neighbor_discovery {
+            if let Some(host) = find_neighbor_host(name, family) {
+                return Response::Success(host);
+            }
         }
+
+        Response::NotFound
     }
 
     fn get_host_by_addr(addr: std::net::IpAddr) -> libnss::interop::Response<Host> {
@@ -170,91 +207,185 @@ impl HostHooks for NsHost {
     }
 }
 
-fn ip_addr(netns: Option<&str>) -> Result::<Vec::<LinkInfo>, Box::<dyn std::error::Error>> {
-    let args = {
-        let mut args = vec!["-json".to_string()];
+/// One synthesized host per reachable neighbor table entry of `ns_name`
+/// whose address passes the allow/deny rules, named via
+/// [`config::Config::neighbor_name`].
+fn neighbor_hosts(ns_name: &str) -> Vec::<Host> {
+    let neighbors = match cached_ip_neigh(Some(ns_name)) {
+        Ok(neighbors) => neighbors,
+        Err(err) => {
+            eprintln!("neighbor_hosts: {}", err);
+            return vec![];
+        },
+    };
 
-        if let Some(netns) = netns {
-            args.extend(vec!["-n".into(), netns.into()].into_iter())
-        }
+    neighbors
+        .into_iter()
+        .filter(Neighbor::is_reachable)
+        .filter(|neighbor| CONFIG.permits(&neighbor.dst))
+        .enumerate()
+        .map(|(index, neighbor)| {
+            let name = CONFIG.neighbor_name(ns_name, &neighbor.dev, index, neighbor.lladdr.as_deref());
+
+            let addresses = match neighbor.dst {
+                IpAddr::V4(addr) => Addresses::V4(vec![addr]),
+                IpAddr::V6(addr) => Addresses::V6(vec![addr]),
+            };
+
+            Host { name, aliases: vec![], addresses }
+        })
+        .collect()
+}
 
-        args.push("address".into());
+/// Forward lookup of a synthesized neighbor hostname: scans every
+/// namespace's neighbor table since its namespace can't be recovered from
+/// the name alone.
+fn find_neighbor_host(name: &str, family: AddressFamily) -> Option<Host> {
+    let ns_names = cached_ip_netns_ls().ok()?;
+
+    ns_names
+        .into_iter()
+        .flat_map(|ns| neighbor_hosts(&ns))
+        .filter(|host| family_matches(&family, &host.addresses))
+        .find(|host| host.name == name)
+}
 
-        args
+fn family_matches(family: &AddressFamily, addresses: &Addresses) -> bool {
+    match family {
+        AddressFamily::IPv6 => matches!(addresses, Addresses::V6(_)),
+        AddressFamily::IPv4 | AddressFamily::Unspecified => matches!(addresses, Addresses::V4(_)),
+    }
+}
+
+/// `AddressFamily` has no `Copy`/`Clone` impl upstream, so this is the
+/// cheapest way to use a borrowed family value where an owned one is needed.
+fn copy_family(family: &AddressFamily) -> AddressFamily {
+    match family {
+        AddressFamily::IPv4 => AddressFamily::IPv4,
+        AddressFamily::IPv6 => AddressFamily::IPv6,
+        AddressFamily::Unspecified => AddressFamily::Unspecified,
+    }
+}
+
+/// Canonical name plus aliases for a namespace: the suffixed form if a
+/// suffix is configured (with the bare name as an alias), otherwise just
+/// the bare name.
+fn canonical_name(ns_name: &str) -> (String, Vec::<String>) {
+    if CONFIG.suffix.is_empty() {
+        (ns_name.to_string(), vec![])
+    } else {
+        (format!("{}{}", ns_name, CONFIG.suffix), vec![ns_name.to_string()])
+    }
+}
+
+fn resolve_host(ns_name: &str, family: AddressFamily) -> Response<Host> {
+    let result = cached_ip_addr(Some(ns_name));
+    if let Err(err) = result {
+        eprintln!("resolve_host: {}", err);
+        return Response::NotFound;
+    }
+
+    let af_filter = match family {
+        AddressFamily::IPv6 => "inet6",
+        AddressFamily::IPv4 | _ => "inet",
     };
 
-    let result =
-        Command::new("ip")
-            .args(args)
-            .output()
+    let result = result.unwrap();
+
+    let addresses: Vec::<IpAddr> =
+        result
+        .into_iter()
+        .filter(|info| CONFIG.include_loopback || info.link_type != "loopback")
+        .map(|link: LinkInfo| {
+            let addr_info: Vec::<IpAddr> =
+                link
+                .addr_info
+                .into_iter()
+                .filter(|addr| addr.family == af_filter)
+                .filter(|addr| !CONFIG.excluded_scopes.iter().any(|scope| scope == &addr.scope))
+                .map(|addr| {
+                    let parsed: IpAddr = addr.local.parse().expect("not an IP address");
+                    parsed
+                })
+                .filter(|addr| CONFIG.permits(addr))
+                .collect();
+            addr_info
+        })
+        .flatten()
+        .collect()
     ;
 
-    let result: Vec::<LinkInfo> = match result {
-        Ok(output) => {
-            let as_string = String::from_utf8(output.stdout)?;
-            let as_unstructured: serde_json::Value = serde_json::from_str(&as_string)?;
-            let as_unstructured_arr = as_unstructured.as_array().ok_or("failed to parse JSON as array")?;
-            let link_infos: Vec::<LinkInfo> = as_unstructured_arr.into_iter().map(|interface_json| {
-                let link_info: LinkInfo = serde_json::from_value(interface_json.clone())?;
-                Ok(link_info)
-            })
-            .filter_map(|info: Result<LinkInfo, Box::<dyn std::error::Error>>| {
-                match info {
-                    Ok(info) => Some(info),
-
-                    Err(err) => {
-                        eprintln!("ip_addr: {:?}: {}", netns, err);
-                        None
-                    },
-                }
-            })
-            .collect();
+    let (name, aliases) = canonical_name(ns_name);
+
+    match family {
+        AddressFamily::IPv6 => {
+            let addresses: Vec::<Ipv6Addr> = addresses.into_iter().map(|a| match a {
+                IpAddr::V6(a) => a,
+                _ => unreachable!(),
+            }).collect();
 
-            link_infos
+            if addresses.is_empty() {
+                return Response::NotFound;
+            }
+
+            Response::Success(Host{
+                name,
+                aliases,
+                addresses: Addresses::V6(addresses),
+            })
         },
 
-        Err(error) => { return Err(Box::new(error)); }
-    };
+        AddressFamily::IPv4 | _ => {
+            let addresses: Vec::<Ipv4Addr> = addresses.into_iter().map(|a| match a {
+                IpAddr::V4(a) => a,
+                _ => unreachable!(),
+            }).collect();
+
+            if addresses.is_empty() {
+                return Response::NotFound;
+            }
 
-    
-    Ok(result)
+            Response::Success(Host{
+                name,
+                aliases,
+                addresses: Addresses::V4(addresses),
+            })
+        },
+    }
 }
 
-fn ip_netns_ls() -> Result::<Vec::<String>, Box::<dyn std::error::Error>> {
-    let args = vec!["-json".to_string(), "netns".into(), "ls".into()];
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let result =
-        Command::new("ip")
-            .args(args)
-            .output()
-    ;
+    #[test]
+    fn canonical_name_without_suffix_has_no_alias() {
+        assert_eq!(canonical_name("web"), ("web".to_string(), vec![]));
+    }
 
-    let result: Vec::<String> = match result {
-        Ok(output) => {
-            let as_string = String::from_utf8(output.stdout)?;
-            let as_unstructured: serde_json::Value = serde_json::from_str(&as_string)?;
-            let as_unstructured_arr = as_unstructured.as_array().ok_or("failed to parse JSON as array")?;
-            let ns_names: Vec::<String> = as_unstructured_arr.into_iter().map(|ns_json| {
-                let ns: Netns = serde_json::from_value(ns_json.clone())?;
-                Ok(ns.name)
-            })
-            .filter_map(|name: Result<String, Box::<dyn std::error::Error>>| {
-                match name {
-                    Ok(n) => Some(n),
-                    Err(err) => {
-                        eprintln!("ip_netns_ls: {}", err);
-                        None
-                    },
-                }
-            })
-            .collect();
+    #[test]
+    fn is_reachable_checks_neighbor_state() {
+        let mut neighbor = Neighbor {
+            dst: "10.0.0.1".parse().unwrap(),
+            dev: "eth0".to_string(),
+            lladdr: None,
+            state: vec!["STALE".to_string()],
+        };
+        assert!(neighbor.is_reachable());
 
-            ns_names
-        },
+        neighbor.state = vec!["FAILED".to_string()];
+        assert!(!neighbor.is_reachable());
+    }
 
-        Err(error) => { return Err(Box::new(error)); }
-    };
+    #[test]
+    fn family_matches_checks_address_variant() {
+        let v4 = Addresses::V4(vec![]);
+        let v6 = Addresses::V6(vec![]);
 
-    
-    Ok(result)
+        assert!(family_matches(&AddressFamily::IPv4, &v4));
+        assert!(!family_matches(&AddressFamily::IPv4, &v6));
+        assert!(family_matches(&AddressFamily::IPv6, &v6));
+        assert!(!family_matches(&AddressFamily::IPv6, &v4));
+        assert!(family_matches(&AddressFamily::Unspecified, &v4));
+    }
 }
@@ -0,0 +1,169 @@
+use std::error::Error;
+use std::process::Command;
+
+use crate::{LinkInfo, Neighbor, Netns};
+
+use super::NetnsSource;
+
+/// Default backend: shells out to `ip -json ...` and parses its output.
+/// Simple and dependency-light, but forks a process per lookup and
+/// requires `iproute2` to be installed and reachable on `PATH`.
+#[derive(Default)]
+#[cfg_attr(feature = "netlink", allow(dead_code))]
+pub struct CommandSource;
+
+impl NetnsSource for CommandSource {
+    fn list_namespaces(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        ip_netns_ls()
+    }
+
+    fn addresses(&self, netns: Option<&str>) -> Result<Vec<LinkInfo>, Box<dyn Error>> {
+        ip_addr(netns)
+    }
+
+    fn neighbors(&self, netns: Option<&str>) -> Result<Vec<Neighbor>, Box<dyn Error>> {
+        ip_neigh(netns)
+    }
+}
+
+#[cfg_attr(feature = "netlink", allow(dead_code))]
+fn ip_addr(netns: Option<&str>) -> Result::<Vec::<LinkInfo>, Box::<dyn std::error::Error>> {
+    let args = {
+        let mut args = vec!["-json".to_string()];
+
+        if let Some(netns) = netns {
+            args.extend(vec!["-n".into(), netns.into()].into_iter())
+        }
+
+        args.push("address".into());
+
+        args
+    };
+
+    let result =
+        Command::new("ip")
+            .args(args)
+            .output()
+    ;
+
+    let result: Vec::<LinkInfo> = match result {
+        Ok(output) => {
+            let as_string = String::from_utf8(output.stdout)?;
+            let as_unstructured: serde_json::Value = serde_json::from_str(&as_string)?;
+            let as_unstructured_arr = as_unstructured.as_array().ok_or("failed to parse JSON as array")?;
+            let link_infos: Vec::<LinkInfo> = as_unstructured_arr.into_iter().map(|interface_json| {
+                let link_info: LinkInfo = serde_json::from_value(interface_json.clone())?;
+                Ok(link_info)
+            })
+            .filter_map(|info: Result<LinkInfo, Box::<dyn std::error::Error>>| {
+                match info {
+                    Ok(info) => Some(info),
+
+                    Err(err) => {
+                        eprintln!("ip_addr: {:?}: {}", netns, err);
+                        None
+                    },
+                }
+            })
+            .collect();
+
+            link_infos
+        },
+
+        Err(error) => { return Err(Box::new(error)); }
+    };
+
+
+    Ok(result)
+}
+
+#[cfg_attr(feature = "netlink", allow(dead_code))]
+fn ip_neigh(netns: Option<&str>) -> Result::<Vec::<Neighbor>, Box::<dyn std::error::Error>> {
+    let args = {
+        let mut args = vec!["-json".to_string()];
+
+        if let Some(netns) = netns {
+            args.extend(vec!["-n".into(), netns.into()].into_iter())
+        }
+
+        args.push("neigh".into());
+
+        args
+    };
+
+    let result =
+        Command::new("ip")
+            .args(args)
+            .output()
+    ;
+
+    let result: Vec::<Neighbor> = match result {
+        Ok(output) => {
+            let as_string = String::from_utf8(output.stdout)?;
+            let as_unstructured: serde_json::Value = serde_json::from_str(&as_string)?;
+            let as_unstructured_arr = as_unstructured.as_array().ok_or("failed to parse JSON as array")?;
+            let neighbors: Vec::<Neighbor> = as_unstructured_arr.into_iter().map(|neigh_json| {
+                let neighbor: Neighbor = serde_json::from_value(neigh_json.clone())?;
+                Ok(neighbor)
+            })
+            .filter_map(|neighbor: Result<Neighbor, Box::<dyn std::error::Error>>| {
+                match neighbor {
+                    Ok(neighbor) => Some(neighbor),
+
+                    Err(err) => {
+                        eprintln!("ip_neigh: {:?}: {}", netns, err);
+                        None
+                    },
+                }
+            })
+            .collect();
+
+            neighbors
+        },
+
+        Err(error) => { return Err(Box::new(error)); }
+    };
+
+
+    Ok(result)
+}
+
+#[cfg_attr(feature = "netlink", allow(dead_code))]
+fn ip_netns_ls() -> Result::<Vec::<String>, Box::<dyn std::error::Error>> {
+    let args = vec!["-json".to_string(), "netns".into(), "ls".into()];
+
+    let result =
+        Command::new("ip")
+            .args(args)
+            .output()
+    ;
+
+    let result: Vec::<String> = match result {
+        Ok(output) => {
+            let as_string = String::from_utf8(output.stdout)?;
+            let as_unstructured: serde_json::Value = serde_json::from_str(&as_string)?;
+            let as_unstructured_arr = as_unstructured.as_array().ok_or("failed to parse JSON as array")?;
+            let ns_names: Vec::<String> = as_unstructured_arr.into_iter().map(|ns_json| {
+                let ns: Netns = serde_json::from_value(ns_json.clone())?;
+                Ok(ns.name)
+            })
+            .filter_map(|name: Result<String, Box::<dyn std::error::Error>>| {
+                match name {
+                    Ok(n) => Some(n),
+                    Err(err) => {
+                        eprintln!("ip_netns_ls: {}", err);
+                        None
+                    },
+                }
+            })
+            .collect();
+
+            ns_names
+        },
+
+        Err(error) => { return Err(Box::new(error)); }
+    };
+
+
+    Ok(result)
+}
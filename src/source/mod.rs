@@ -0,0 +1,23 @@
+use std::error::Error;
+
+use crate::{LinkInfo, Neighbor};
+
+pub mod command;
+
+#[cfg(feature = "netlink")]
+pub mod netlink;
+
+/// Where namespace names, interface addresses and neighbor table entries
+/// come from; lets the subprocess and netlink backends be interchangeable.
+pub trait NetnsSource {
+    fn list_namespaces(&self) -> Result<Vec<String>, Box<dyn Error>>;
+    fn addresses(&self, netns: Option<&str>) -> Result<Vec<LinkInfo>, Box<dyn Error>>;
+    fn neighbors(&self, netns: Option<&str>) -> Result<Vec<Neighbor>, Box<dyn Error>>;
+}
+
+/// Backend selected at compile time via the `netlink` feature.
+#[cfg(not(feature = "netlink"))]
+pub type ActiveSource = command::CommandSource;
+
+#[cfg(feature = "netlink")]
+pub type ActiveSource = netlink::NetlinkSource;
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::os::unix::io::AsRawFd;
+use std::sync::mpsc;
+use std::thread;
+
+use futures::stream::TryStreamExt;
+use netlink_packet_route::address::Nla as AddressNla;
+use netlink_packet_route::link::nlas::Nla as LinkNla;
+use netlink_packet_route::neighbour::Nla as NeighbourNla;
+use netlink_packet_route::{
+    AF_INET6, ARPHRD_LOOPBACK,
+    RT_SCOPE_HOST, RT_SCOPE_LINK, RT_SCOPE_NOWHERE, RT_SCOPE_SITE, RT_SCOPE_UNIVERSE,
+};
+use rtnetlink::new_connection;
+use rtnetlink::Handle;
+
+use crate::{LinkAddress, LinkInfo, Neighbor};
+
+use super::NetnsSource;
+
+const NETNS_DIR: &str = "/var/run/netns";
+
+/// Reads interface addresses straight off an `RTM_GETADDR` dump instead of
+/// forking `ip`. Namespaces are entered with `setns(2)` from a disposable
+/// helper thread (the same approach Fuchsia's `net-cli` and similar tools
+/// use), so the caller's own namespace membership is never touched.
+#[derive(Default)]
+pub struct NetlinkSource;
+
+impl NetnsSource for NetlinkSource {
+    fn list_namespaces(&self) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut names = vec![];
+
+        for entry in fs::read_dir(NETNS_DIR)? {
+            if let Some(name) = entry?.file_name().to_str() {
+                names.push(name.to_string());
+            }
+        }
+
+        Ok(names)
+    }
+
+    fn addresses(&self, netns: Option<&str>) -> Result<Vec<LinkInfo>, Box<dyn Error>> {
+        let netns = netns.map(String::from);
+        let (tx, rx) = mpsc::channel();
+
+        // setns(2) only affects the calling thread, so the switch happens
+        // in a throwaway thread: once it exits, nothing about the caller's
+        // own namespace membership has changed.
+        let worker = thread::spawn(move || {
+            let result = dump(netns.as_deref()).map_err(|err| err.to_string());
+            let _ = tx.send(result);
+        });
+
+        worker.join().map_err(|_| "netlink helper thread panicked")?;
+        rx.recv()?.map_err(|err| err.into())
+    }
+
+    fn neighbors(&self, netns: Option<&str>) -> Result<Vec<Neighbor>, Box<dyn Error>> {
+        let netns = netns.map(String::from);
+        let (tx, rx) = mpsc::channel();
+
+        let worker = thread::spawn(move || {
+            let result = dump_neigh(netns.as_deref()).map_err(|err| err.to_string());
+            let _ = tx.send(result);
+        });
+
+        worker.join().map_err(|_| "netlink helper thread panicked")?;
+        rx.recv()?.map_err(|err| err.into())
+    }
+}
+
+fn dump(netns: Option<&str>) -> Result<Vec<LinkInfo>, Box<dyn Error + Send + Sync>> {
+    if let Some(name) = netns {
+        enter_netns(name)?;
+    }
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    rt.block_on(dump_addresses())
+}
+
+fn dump_neigh(netns: Option<&str>) -> Result<Vec<Neighbor>, Box<dyn Error + Send + Sync>> {
+    if let Some(name) = netns {
+        enter_netns(name)?;
+    }
+
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    rt.block_on(dump_neighbors())
+}
+
+fn enter_netns(name: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let file = fs::File::open(format!("{}/{}", NETNS_DIR, name))?;
+    nix::sched::setns(file.as_raw_fd(), nix::sched::CloneFlags::CLONE_NEWNET)?;
+    Ok(())
+}
+
+async fn dump_addresses() -> Result<Vec<LinkInfo>, Box<dyn Error + Send + Sync>> {
+    let (connection, handle, _) = new_connection()?;
+    tokio::spawn(connection);
+
+    let mut links = std::collections::HashMap::<u32, LinkInfo>::new();
+
+    let mut link_req = handle.link().get().execute();
+    while let Some(msg) = link_req.try_next().await? {
+        let link_type = if msg.header.link_layer_type == ARPHRD_LOOPBACK {
+            "loopback"
+        } else {
+            "ether"
+        };
+
+        links.insert(msg.header.index, LinkInfo {
+            link_type: link_type.to_string(),
+            addr_info: vec![],
+        });
+    }
+
+    let mut addr_req = handle.address().get().execute();
+    while let Some(msg) = addr_req.try_next().await? {
+        let index = msg.header.index;
+        let family = if u16::from(msg.header.family) == AF_INET6 { "inet6" } else { "inet" };
+        let scope = scope_name(msg.header.scope);
+
+        for nla in &msg.nlas {
+            let bytes = match nla {
+                AddressNla::Address(bytes) | AddressNla::Local(bytes) => bytes,
+                _ => continue,
+            };
+
+            let local = match format_addr(family, bytes) {
+                Some(local) => local,
+                None => continue,
+            };
+
+            if let Some(link) = links.get_mut(&index) {
+                link.addr_info.push(LinkAddress {
+                    family: family.to_string(),
+                    local,
+                    scope: scope.to_string(),
+                });
+            }
+        }
+    }
+
+    Ok(links.into_values().collect())
+}
+
+async fn dump_neighbors() -> Result<Vec<Neighbor>, Box<dyn Error + Send + Sync>> {
+    let (connection, handle, _) = new_connection()?;
+    tokio::spawn(connection);
+
+    let dev_names = link_names(&handle).await?;
+
+    let mut neighbors = vec![];
+    let mut neigh_req = handle.neighbours().get().execute();
+
+    while let Some(msg) = neigh_req.try_next().await? {
+        let dev = dev_names.get(&msg.header.ifindex).cloned().unwrap_or_default();
+        let state = neighbor_state_names(msg.header.state);
+
+        let mut dst = None;
+        let mut lladdr = None;
+
+        for nla in &msg.nlas {
+            match nla {
+                NeighbourNla::Destination(bytes) => dst = parse_ip(bytes),
+                NeighbourNla::LinkLocalAddress(bytes) => lladdr = Some(format_lladdr(bytes)),
+                _ => {},
+            }
+        }
+
+        if let Some(dst) = dst {
+            neighbors.push(Neighbor { dst, dev, lladdr, state });
+        }
+    }
+
+    Ok(neighbors)
+}
+
+async fn link_names(handle: &Handle) -> Result<HashMap<u32, String>, Box<dyn Error + Send + Sync>> {
+    let mut names = HashMap::new();
+    let mut link_req = handle.link().get().execute();
+
+    while let Some(msg) = link_req.try_next().await? {
+        for nla in msg.nlas {
+            if let LinkNla::IfName(name) = nla {
+                names.insert(msg.header.index, name);
+            }
+        }
+    }
+
+    Ok(names)
+}
+
+// Subset of the NUD_* neighbour states (see `man 7 rtnetlink`).
+const NUD_INCOMPLETE: u16 = 0x01;
+const NUD_REACHABLE: u16 = 0x02;
+const NUD_STALE: u16 = 0x04;
+const NUD_DELAY: u16 = 0x08;
+const NUD_FAILED: u16 = 0x20;
+
+fn neighbor_state_names(state: u16) -> Vec<String> {
+    vec![
+        (NUD_INCOMPLETE, "INCOMPLETE"),
+        (NUD_REACHABLE, "REACHABLE"),
+        (NUD_STALE, "STALE"),
+        (NUD_DELAY, "DELAY"),
+        (NUD_FAILED, "FAILED"),
+    ]
+        .into_iter()
+        .filter(|(bit, _)| state & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+fn parse_ip(bytes: &[u8]) -> Option<IpAddr> {
+    match bytes.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]))),
+
+        16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(bytes);
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        },
+
+        _ => None,
+    }
+}
+
+fn format_lladdr(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":")
+}
+
+// Mirrors the scope names `ip`-the-CLI prints (see iproute2's `rt_scopes`).
+fn scope_name(scope: u8) -> &'static str {
+    match scope {
+        RT_SCOPE_UNIVERSE => "global",
+        RT_SCOPE_SITE => "site",
+        RT_SCOPE_LINK => "link",
+        RT_SCOPE_HOST => "host",
+        RT_SCOPE_NOWHERE => "nowhere",
+        _ => "unknown",
+    }
+}
+
+fn format_addr(family: &str, bytes: &[u8]) -> Option<String> {
+    match family {
+        "inet" if bytes.len() == 4 => Some(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]).to_string()),
+
+        "inet6" if bytes.len() == 16 => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(bytes);
+            Some(Ipv6Addr::from(octets).to_string())
+        },
+
+        _ => None,
+    }
+}